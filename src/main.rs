@@ -1,22 +1,32 @@
 #[allow(dead_code)]
 mod util;
 
-use std::time::Duration;
-use std::sync::mpsc;
-use std::thread;
-
-use crossterm::{input, AlternateScreen, InputEvent, KeyEvent};
+use std::time::{Duration, Instant};
+use std::io::{self, Write as _};
+use std::fs;
+use std::path::PathBuf;
+
+use crossterm::execute;
+use crossterm::terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::event::{self, Event as CEvent, KeyCode};
 use tui::Terminal;
 use tui::backend::CrosstermBackend;
 use tui::style::{Style, Color};
-use tui::layout::{Constraint, Layout};
-use tui::widgets::{Widget, Block, Borders, SelectableList, canvas::Canvas};
+use tui::layout::{Constraint, Direction as LayoutDirection, Layout};
+use tui::widgets::{Widget, Block, Borders, SelectableList, Table, Row, canvas::Canvas};
+use serde::{Serialize, Deserialize};
 use rand::prelude::*;
 
-enum Event<I> {
-    Input(I),
-    Tick,
-}
+// The number of entries kept in the leaderboard file
+const LEADERBOARD_SIZE: usize = 10;
+// The name runs are recorded under on the leaderboard
+const PLAYER_NAME: &str = "you";
+// How many segments the snake must gain before the tick period drops a step
+const SEGMENTS_PER_SPEEDUP: usize = 5;
+// How much the tick period drops per speed-up step, in milliseconds
+const SPEEDUP_STEP_MS: u64 = 10;
+// The fastest the game is allowed to tick, in milliseconds
+const MIN_TICK_MS: u64 = 40;
 
 use ItemType::*;
 
@@ -52,11 +62,176 @@ struct Item {
 struct App {
     segments: Vec<Segment>,
     items: Vec<Item>,
+    obstacles: Vec<(f64, f64)>,
+    level_walls: Vec<(u16, u16)>,
     playing: bool,
+    game_over: bool,
+    wrap_around: bool,
+    score: u32,
     canvas_x_length: f64,
     canvas_y_length: f64,
 }
 
+// A single row in the persisted high-score table
+#[derive(Serialize, Deserialize, Clone)]
+struct ScoreEntry {
+    name: String,
+    score: u32,
+    date: String,
+}
+
+// The persisted, user-editable configuration
+#[derive(Serialize, Deserialize, Clone)]
+struct Settings {
+    base_tick_ms: u64,
+    starting_length: usize,
+    wrap_around: bool,
+    speed_up: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            base_tick_ms: 100,
+            starting_length: 13,
+            wrap_around: true,
+            speed_up: false,
+        }
+    }
+}
+
+// Function to locate the config file in the user's config directory
+fn settings_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("rusty-snake");
+    path.push("config.json5");
+    path
+}
+
+// Function to load the settings, falling back to the defaults if none are saved
+fn load_settings() -> Settings {
+    match fs::read_to_string(settings_path()) {
+        Ok(contents) => json5::from_str(&contents).unwrap_or_default(),
+        Err(_) => Settings::default(),
+    }
+}
+
+// Function to persist the settings as JSON5, writing atomically
+fn save_settings(settings: &Settings) {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(serialized) = json5::to_string(settings) {
+        let tmp = path.with_extension("json5.tmp");
+        if fs::File::create(&tmp)
+            .and_then(|mut f| f.write_all(serialized.as_bytes()).and_then(|_| f.sync_all()))
+            .is_ok()
+        {
+            let _ = fs::rename(&tmp, &path);
+        }
+    }
+}
+
+// Function to compute the current tick period from the settings and snake length
+fn current_tick(settings: &Settings, length: usize) -> Duration {
+    let mut ms = settings.base_tick_ms;
+    if settings.speed_up {
+        // Drop one step for every SEGMENTS_PER_SPEEDUP segments gained since the start
+        let grown = length.saturating_sub(settings.starting_length);
+        let steps = (grown / SEGMENTS_PER_SPEEDUP) as u64;
+        ms = ms.saturating_sub(steps * SPEEDUP_STEP_MS);
+    }
+    // Never tick faster than the floor, whatever the (possibly hand-edited) config says
+    Duration::from_millis(ms.max(MIN_TICK_MS))
+}
+
+// A loadable level layout: a named grid of walls plus a recommended speed
+#[derive(Serialize, Deserialize, Clone)]
+struct Level {
+    name: String,
+    recommended_speed: u64,
+    walls: Vec<(u16, u16)>,
+}
+
+// Function to locate the directory level files are read from
+fn levels_dir() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("rusty-snake");
+    path.push("levels");
+    path
+}
+
+// Function to load the available levels, always offering an empty map first
+fn load_levels() -> Vec<Level> {
+    let mut levels = vec![Level {
+        name: "Open Field".to_string(),
+        recommended_speed: 100,
+        walls: Vec::new(),
+    }];
+    if let Ok(entries) = fs::read_dir(levels_dir()) {
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "json5"))
+            .collect();
+        // Read the files in a stable order so the menu doesn't shuffle
+        files.sort();
+        for path in files {
+            if let Ok(contents) = fs::read_to_string(&path) {
+                if let Ok(mut level) = json5::from_str::<Level>(&contents) {
+                    // Don't let a hand-written map drive the loop below the tick floor
+                    level.recommended_speed = level.recommended_speed.max(MIN_TICK_MS);
+                    levels.push(level);
+                }
+            }
+        }
+    }
+    levels
+}
+
+// Function to locate the leaderboard file in the user's data directory
+fn leaderboard_path() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("rusty-snake");
+    path.push("leaderboard.json");
+    path
+}
+
+// Function to load the leaderboard, returning an empty list if it doesn't exist yet
+fn load_leaderboard() -> Vec<ScoreEntry> {
+    match fs::read_to_string(leaderboard_path()) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Function to record a score, keeping only the top entries sorted descending
+fn record_score(name: &str, score: u32) {
+    let mut entries = load_leaderboard();
+    entries.push(ScoreEntry {
+        name: name.to_string(),
+        score,
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    });
+    entries.sort_by(|a, b| b.score.cmp(&a.score));
+    entries.truncate(LEADERBOARD_SIZE);
+    // Write atomically so a crash mid-save can't corrupt the table
+    let path = leaderboard_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(serialized) = serde_json::to_string_pretty(&entries) {
+        let tmp = path.with_extension("json.tmp");
+        if fs::File::create(&tmp)
+            .and_then(|mut f| f.write_all(serialized.as_bytes()).and_then(|_| f.sync_all()))
+            .is_ok()
+        {
+            let _ = fs::rename(&tmp, &path);
+        }
+    }
+}
+
 // Macro to generate random coordinates on the canvas
 macro_rules! random_coordinates {
     ($rng:expr, $x_length:expr, $y_length:expr) => {
@@ -97,30 +272,47 @@ impl Default for Segment {
 }
 
 impl App {
-    fn new() -> App {
+    fn new(settings: &Settings) -> App {
         App {
-            segments: vec![
-                Segment::default(),
-                Segment { x: 2.0, ..Default::default() },
-                Segment { x: 3.0, ..Default::default() },
-                Segment { x: 4.0, ..Default::default() },
-                Segment { x: 5.0, ..Default::default() },
-                Segment { x: 6.0, ..Default::default() },
-                Segment { x: 7.0, ..Default::default() },
-                Segment { x: 8.0, ..Default::default() },
-                Segment { x: 9.0, ..Default::default() },
-                Segment { x: 10.0, ..Default::default() },
-                Segment { x: 11.0, ..Default::default() },
-                Segment { x: 12.0, ..Default::default() },
-                Segment { x: 13.0, ..Default::default() },
-            ],
+            // Lay the snake out horizontally from the left edge, head last
+            segments: (0..settings.starting_length)
+                .map(|i| Segment { x: (i + 1) as f64, ..Default::default() })
+                .collect(),
             items: Vec::new(),
+            obstacles: Vec::new(),
+            level_walls: Vec::new(),
             playing: false,
+            game_over: false,
+            wrap_around: settings.wrap_around,
+            score: 0,
             canvas_x_length: 10.0,
             canvas_y_length: 10.0,
         }
     }
 
+    // Function to end the current run, recording the score to the leaderboard
+    fn game_over(&mut self) {
+        record_score(PLAYER_NAME, self.score);
+        self.playing = false;
+        self.game_over = true;
+    }
+
+    // Function to load a level's wall layout for the current run
+    fn set_level(&mut self, level: &Level) {
+        self.level_walls = level.walls.clone();
+    }
+
+    // Function to (re)build the wall coordinates, clamped to the current canvas size
+    fn rebuild_obstacles(&mut self) {
+        self.obstacles = self.level_walls.iter().map(|&(x, y)| {
+            // Clamp the coordinates so smaller terminals still show every wall
+            (
+                (x as f64).min(self.canvas_x_length - 1.0).max(0.0),
+                (y as f64).min(self.canvas_y_length - 1.0).max(0.0),
+            )
+        }).collect();
+    }
+
     // Function to set the direction the snake should head in
     fn set_heading(&mut self, direction: Direction) {
         // Find the index of the head segment
@@ -134,35 +326,16 @@ impl App {
         let mut generate_destructive_item = false;
         let mut rng = rand::thread_rng();
         loop {
-            // Generate random coordinates for the new item
-            let (mut x, mut y) = random_coordinates!(rng, self.canvas_x_length - 1.0, self.canvas_y_length - 1.0);
-            // Loop to see if the generated coordinates are free
-            loop {
-                // Check if the coordinates are occupied by the snake
-                for segment in &self.segments {
-                    if segment.x == x && segment.y == y {
-                        // The coordinates aren't free; try again
-                        let new_coordinates = random_coordinates!(rng, self.canvas_x_length - 1.0, self.canvas_y_length - 1.0);
-                        x = new_coordinates.0;
-                        y = new_coordinates.1;
-                        // Skip to the next iteration
-                        continue;
-                    }
+            // Keep drawing coordinates until they're clear of the snake, items and walls
+            let (x, y) = loop {
+                let (x, y) = random_coordinates!(rng, self.canvas_x_length - 1.0, self.canvas_y_length - 1.0);
+                let occupied = self.segments.iter().any(|segment| segment.x == x && segment.y == y)
+                    || self.items.iter().any(|item| item.x == x && item.y == y)
+                    || self.obstacles.iter().any(|&(ox, oy)| ox == x && oy == y);
+                if !occupied {
+                    break (x, y);
                 }
-                // Check if the coordinates are occupied by other items
-                for item in &self.items {
-                    if item.x == x && item.y == y {
-                        // The coordinates aren't free; try again
-                        let new_coordinates = random_coordinates!(rng, self.canvas_x_length - 1.0, self.canvas_y_length - 1.0);
-                        x = new_coordinates.0;
-                        y = new_coordinates.1;
-                        // Skip to the next iteration
-                        continue;
-                    }
-                }
-                // The coordinates are free; break out of the loop
-                break;
-            }
+            };
             // Add the new item to the app instance
             let items = if generate_destructive_item {
                 vec![Hedgehog, Boulder]
@@ -185,6 +358,21 @@ impl App {
 
     // Function that's called every tick
     fn update(&mut self) {
+        // In solid-wall mode, end the run if the head is about to cross a boundary
+        if !self.wrap_around {
+            let head = self.segments[self.segments.len() - 1];
+            let hits_wall = match head.direction {
+                Direction::Up => head.y + 1.0 >= self.canvas_y_length,
+                Direction::Right => head.x + 1.0 >= self.canvas_x_length,
+                Direction::Down => head.y - 1.0 < 0.0,
+                Direction::Left => head.x - 1.0 < 0.0,
+            };
+            if hits_wall {
+                self.game_over();
+                return;
+            }
+        }
+
         // Move all the snake's segments 1 space in their respective directions
         for i in 0..self.segments.len() {
             match self.segments[i].direction {
@@ -202,6 +390,14 @@ impl App {
         // Get the coordinates of the head
         let (head_x, head_y) = (self.segments[self.segments.len() - 1].x, self.segments[self.segments.len() - 1].y);
 
+        // Check if the head ran into a wall
+        for &(wall_x, wall_y) in &self.obstacles {
+            if head_x == wall_x && head_y == wall_y {
+                self.game_over();
+                return;
+            }
+        }
+
         // Check if the head's in the same space as any item
         for i in 0..self.items.len() {
             if head_x == self.items[i].x && head_y == self.items[i].y {
@@ -218,12 +414,17 @@ impl App {
                         }
                         // Add the tail segment to the app instance
                         self.segments.insert(0, Segment { x, y, direction });
+                        // Reward the player for eating
+                        self.score += 1;
                     }
                     Hedgehog => {
                         // Remove the tail segment
-                        // TODO end game if the snake's length is 0
+                        self.score = self.score.saturating_sub(1);
                         if self.segments.len() > 1 {
                             self.segments.remove(0);
+                        } else {
+                            // The snake has nothing left to lose; end the run
+                            self.game_over();
                         }
                     }
                     Boulder => {
@@ -280,52 +481,41 @@ impl App {
 }
 
 fn main() -> Result<(), failure::Error> {
-    let screen = AlternateScreen::to_alternate(true)?;
-    let backend = CrosstermBackend::with_alternate_screen(screen)?;
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
+    // Load the persisted settings
+    let mut settings = load_settings();
     // Create app instance
-    let mut app = App::new();
+    let mut app = App::new(&settings);
     // Variable to keep track of whether a game is currently in progress
     let mut game_in_progress = false;
     // Variable to keep track of when new items should be generated
     let mut need_items_in = 0;
     // Variable to keep track of the selected option in the menu
     let mut selected_option = 0;
-
-    // Setup input handling
-    let (tx, rx) = mpsc::channel();
-    {
-        let tx = tx.clone();
-        thread::spawn(move || {
-            let input = input();
-            let reader = input.read_sync();
-            for event in reader {
-                match event {
-                    InputEvent::Keyboard(key) => {
-                        if let Err(_) = tx.send(Event::Input(key.clone())) {
-                            return;
-                        }
-                        if key == KeyEvent::Char('q') {
-                            return;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        });
-    }
-    {
-        let tx = tx.clone();
-        thread::spawn(move || {
-            let tx = tx.clone();
-            loop {
-                tx.send(Event::Tick).unwrap();
-                thread::sleep(Duration::from_millis(100));
-            }
-        });
-    }
+    // Variable to keep track of whether the leaderboard screen is showing
+    let mut showing_leaderboard = false;
+    // Variable to keep track of whether the settings screen is showing
+    let mut showing_settings = false;
+    // Variable to keep track of the selected option in the settings screen
+    let mut settings_selected = 0;
+    // Variable to keep track of whether the level-selection screen is showing
+    let mut showing_levels = false;
+    // Variable to keep track of the selected level
+    let mut level_selected = 0;
+    // Base tick override coming from the chosen level's recommended speed
+    let mut level_speed_override: Option<u64> = None;
+    // Variable to keep track of the selected option on the game-over screen
+    let mut game_over_selected = 0;
+    // Variable to keep track of whether the game is paused
+    let mut paused = false;
+    // Instant the last tick fired, used to pace the single-threaded loop
+    let mut last_tick = Instant::now();
 
     terminal.clear()?;
 
@@ -339,13 +529,8 @@ fn main() -> Result<(), failure::Error> {
                     .split(size);
                 app.canvas_x_length = size.width as f64;
                 app.canvas_y_length = size.height as f64;
-
-                if need_items_in == 0 {
-                    app.generate_item();
-                    need_items_in = 15;
-                }
-
-                need_items_in -= 1;
+                // Fit the level's walls to the current terminal size
+                app.rebuild_obstacles();
 
                 Canvas::default()
                     .block(Block::default().borders(Borders::NONE))
@@ -376,6 +561,10 @@ fn main() -> Result<(), failure::Error> {
                             }
                         }
 
+                        for &(wall_x, wall_y) in &app.obstacles {
+                            ctx.print(wall_x, wall_y, "ðŸ§±", Color::Indexed(95));
+                        }
+
                         for item in &app.items {
                             match item.item_type {
                                 Apple => ctx.print(item.x, item.y, "ðŸŽ", Color::Indexed(160)),
@@ -388,37 +577,279 @@ fn main() -> Result<(), failure::Error> {
                 .x_bounds([0.0, size.width as f64])
                     .y_bounds([0.0, size.height as f64])
                     .render(&mut f, rects[0]);
+
+                // Draw a "Paused" overlay in the centre of the canvas
+                if paused {
+                    let vertical = Layout::default()
+                        .direction(LayoutDirection::Vertical)
+                        .constraints([Constraint::Percentage(45), Constraint::Length(3), Constraint::Percentage(45)].as_ref())
+                        .split(size);
+                    let horizontal = Layout::default()
+                        .direction(LayoutDirection::Horizontal)
+                        .constraints([Constraint::Percentage(35), Constraint::Percentage(30), Constraint::Percentage(35)].as_ref())
+                        .split(vertical[1]);
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Paused (space)")
+                        .render(&mut f, horizontal[1]);
+                }
             })?;
 
-            let current_heading = app.segments[app.segments.len() - 1].direction;
-            match rx.recv()? {
-                Event::Input(input) => match input {
-                    KeyEvent::Char('q') => {
-                        // Quit the program
-                        break;
+            // Compute how long to wait for input before the next tick is due,
+            // letting the chosen level override the base tick
+            let tick_period = {
+                let mut run_settings = settings.clone();
+                if let Some(ms) = level_speed_override {
+                    run_settings.base_tick_ms = ms;
+                }
+                current_tick(&run_settings, app.segments.len())
+            };
+            let timeout = tick_period.checked_sub(last_tick.elapsed()).unwrap_or_default();
+            if event::poll(timeout)? {
+                if let CEvent::Key(key) = event::read()? {
+                    let current_heading = app.segments[app.segments.len() - 1].direction;
+                    match key.code {
+                        KeyCode::Char('q') => {
+                            // Quit the program
+                            break;
+                        }
+                        KeyCode::Char(' ') => paused = !paused,
+                        KeyCode::Up => if !paused && current_heading != Direction::Down {
+                            // Change the snake's head's direction to up
+                            app.set_heading(Direction::Up)
+                        }
+                        KeyCode::Right => if !paused && current_heading != Direction::Left {
+                            // Change the snake's head's direction to right
+                            app.set_heading(Direction::Right)
+                        }
+                        KeyCode::Down => if !paused && current_heading != Direction::Up {
+                            // Change the snake's head's direction to down
+                            app.set_heading(Direction::Down)
+                        }
+                        KeyCode::Left => if !paused && current_heading != Direction::Right {
+                            // Change the snake's head's direction to left
+                            app.set_heading(Direction::Left)
+                        }
+                        _ => {}
                     }
-                    KeyEvent::Up => if current_heading != Direction::Down {
-                        // Change the snake's head's direction to up
-                        app.set_heading(Direction::Up)
+                }
+            }
+
+            // Advance the game exactly when the tick deadline elapses
+            if last_tick.elapsed() >= tick_period {
+                if !paused {
+                    if need_items_in == 0 {
+                        app.generate_item();
+                        need_items_in = 15;
                     }
-                    KeyEvent::Right => if current_heading != Direction::Left {
-                        // Change the snake's head's direction to right
-                        app.set_heading(Direction::Right)
+                    need_items_in -= 1;
+                    app.update();
+                }
+                last_tick = Instant::now();
+            }
+        } else if app.game_over {
+            let game_over_items = vec!["Restart        (r)", "Back to menu   (m)"];
+            let title = format!("Game Over - final score: {}", app.score);
+            terminal.draw(|mut f| {
+                let chunks = Layout::default()
+                    .margin(5)
+                    .constraints([Constraint::Percentage(100)].as_ref())
+                    .split(f.size());
+                SelectableList::default()
+                    .block(Block::default().borders(Borders::ALL).title(&title))
+                    .items(&game_over_items)
+                    .select(Some(game_over_selected))
+                    .style(Style::default().fg(Color::Indexed(204)))
+                    .highlight_style(Style::default().fg(Color::Indexed(207)))
+                    .highlight_symbol("→")
+                    .render(&mut f, chunks[0]);
+            })?;
+
+            if let CEvent::Key(key) = event::read()? {
+                // Restart when the first option is chosen, return to the menu otherwise
+                let restart = matches!(key.code, KeyCode::Char('r'))
+                    || (matches!(key.code, KeyCode::Enter) && game_over_selected == 0);
+                let to_menu = matches!(key.code, KeyCode::Char('m'))
+                    || (matches!(key.code, KeyCode::Enter) && game_over_selected == 1);
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Up => game_over_selected = if game_over_selected > 0 {
+                        game_over_selected - 1
+                    } else {
+                        game_over_items.len() - 1
+                    },
+                    KeyCode::Down => game_over_selected = if game_over_selected >= game_over_items.len() - 1 {
+                        0
+                    } else {
+                        game_over_selected + 1
+                    },
+                    _ if restart => {
+                        // Start a fresh run on the same level
+                        let walls = app.level_walls.clone();
+                        app = App::new(&settings);
+                        app.level_walls = walls;
+                        app.playing = true;
+                        game_in_progress = true;
+                        paused = false;
+                        game_over_selected = 0;
+                        last_tick = Instant::now();
                     }
-                    KeyEvent::Down => if current_heading != Direction::Up {
-                        // Change the snake's head's direction to down
-                        app.set_heading(Direction::Down)
+                    _ if to_menu => {
+                        // Return to the menu with a clean slate
+                        app = App::new(&settings);
+                        game_in_progress = false;
+                        level_speed_override = None;
+                        game_over_selected = 0;
+                    }
+                    // Any other key is a no-op
+                    _ => {}
+                }
+            }
+        } else if showing_levels {
+            let levels = load_levels();
+            // Keep the cursor within range if the level list shrank
+            if level_selected >= levels.len() {
+                level_selected = 0;
+            }
+            let level_items: Vec<String> = levels.iter()
+                .map(|level| format!("{:<16} {} walls", level.name, level.walls.len()))
+                .collect();
+            terminal.draw(|mut f| {
+                let chunks = Layout::default()
+                    .margin(5)
+                    .constraints([Constraint::Percentage(100)].as_ref())
+                    .split(f.size());
+                SelectableList::default()
+                    .block(Block::default().borders(Borders::ALL).title("Level Select (enter to play)"))
+                    .items(&level_items)
+                    .select(Some(level_selected))
+                    .style(Style::default().fg(Color::Indexed(204)))
+                    .highlight_style(Style::default().fg(Color::Indexed(207)))
+                    .highlight_symbol("→")
+                    .render(&mut f, chunks[0]);
+            })?;
+
+            if let CEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Up => level_selected = if level_selected > 0 {
+                        level_selected - 1
+                    } else {
+                        levels.len() - 1
+                    },
+                    KeyCode::Down => level_selected = if level_selected >= levels.len() - 1 {
+                        0
+                    } else {
+                        level_selected + 1
+                    },
+                    KeyCode::Enter | KeyCode::Char('n') => {
+                        // Start a new game on the selected level
+                        let level = &levels[level_selected];
+                        app = App::new(&settings);
+                        app.set_level(level);
+                        app.playing = true;
+                        game_in_progress = true;
+                        paused = false;
+                        level_speed_override = Some(level.recommended_speed);
+                        last_tick = Instant::now();
+                        showing_levels = false;
                     }
-                    KeyEvent::Left => if current_heading != Direction::Right {
-                        // Change the snake's head's direction to left
-                        app.set_heading(Direction::Left)
+                    // Any other key returns to the menu
+                    _ => showing_levels = false,
+                }
+            }
+        } else if showing_leaderboard {
+            let entries = load_leaderboard();
+            terminal.draw(|mut f| {
+                let chunks = Layout::default()
+                    .margin(5)
+                    .constraints([Constraint::Percentage(100)].as_ref())
+                    .split(f.size());
+                let rows = entries.iter().enumerate().map(|(i, entry)| {
+                    Row::Data(vec![
+                        format!("{}", i + 1),
+                        entry.name.clone(),
+                        format!("{}", entry.score),
+                        entry.date.clone(),
+                    ].into_iter())
+                });
+                Table::new(
+                    ["#", "Name", "Score", "Date"].iter(),
+                    rows,
+                )
+                    .block(Block::default().borders(Borders::ALL).title("Leaderboards"))
+                    .header_style(Style::default().fg(Color::Indexed(207)))
+                    .style(Style::default().fg(Color::Indexed(204)))
+                    .widths(&[5, 16, 8, 12])
+                    .render(&mut f, chunks[0]);
+            })?;
+
+            if let CEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    // Any other key returns to the menu
+                    _ => showing_leaderboard = false,
+                }
+            }
+        } else if showing_settings {
+            let setting_items = vec![
+                format!("Base tick (ms)        {}", settings.base_tick_ms),
+                format!("Starting length       {}", settings.starting_length),
+                format!("Wrap around walls      {}", if settings.wrap_around { "on" } else { "off" }),
+                format!("Speed up as you grow   {}", if settings.speed_up { "on" } else { "off" }),
+            ];
+            terminal.draw(|mut f| {
+                let chunks = Layout::default()
+                    .margin(5)
+                    .constraints([Constraint::Percentage(100)].as_ref())
+                    .split(f.size());
+                SelectableList::default()
+                    .block(Block::default().borders(Borders::ALL).title("Settings (←/→ to change, s to save)"))
+                    .items(&setting_items)
+                    .select(Some(settings_selected))
+                    .style(Style::default().fg(Color::Indexed(204)))
+                    .highlight_style(Style::default().fg(Color::Indexed(207)))
+                    .highlight_symbol("→")
+                    .render(&mut f, chunks[0]);
+            })?;
+
+            if let CEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Char('s') => {
+                        // Save the settings and return to the menu
+                        save_settings(&settings);
+                        showing_settings = false;
                     }
+                    KeyCode::Up => settings_selected = if settings_selected > 0 {
+                        settings_selected - 1
+                    } else {
+                        setting_items.len() - 1
+                    },
+                    KeyCode::Down => settings_selected = if settings_selected >= setting_items.len() - 1 {
+                        0
+                    } else {
+                        settings_selected + 1
+                    },
+                    KeyCode::Left => match settings_selected {
+                        0 => settings.base_tick_ms = settings.base_tick_ms.saturating_sub(10).max(MIN_TICK_MS),
+                        1 => settings.starting_length = settings.starting_length.saturating_sub(1).max(1),
+                        2 => settings.wrap_around = !settings.wrap_around,
+                        3 => settings.speed_up = !settings.speed_up,
+                        _ => {}
+                    },
+                    KeyCode::Right => match settings_selected {
+                        0 => settings.base_tick_ms += 10,
+                        1 => settings.starting_length += 1,
+                        2 => settings.wrap_around = !settings.wrap_around,
+                        3 => settings.speed_up = !settings.speed_up,
+                        _ => {}
+                    },
                     _ => {}
                 }
-                Event::Tick => app.update()
             }
         } else {
-            let mut menu_items = vec!["New Game       (n)", "Leaderboards   (l)", "Settings       (s)", "Help           (h)", "Quit           (q)"];
+            let mut menu_items = vec!["New Game       (n)", "Level Select   (v)", "Leaderboards   (l)", "Settings       (s)", "Help           (h)", "Quit           (q)"];
             terminal.draw(|mut f| {
                 // Draw menu
                 if game_in_progress {
@@ -438,33 +869,53 @@ fn main() -> Result<(), failure::Error> {
                     .render(&mut f, chunks[0]);
             })?;
 
-            match rx.recv()? {
-                Event::Input(input) => match input {
-                    KeyEvent::Char('q') => {
+            if let CEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') => {
                         // Quit the program
                         break;
                     }
-                    KeyEvent::Char('n') => {
-                        // Start a new game
+                    KeyCode::Char('n') => {
+                        // Start a new game from a clean slate on the open field
+                        app = App::new(&settings);
                         app.playing = true;
                         game_in_progress = true;
+                        paused = false;
+                        level_speed_override = None;
+                        last_tick = Instant::now();
+                    }
+                    KeyCode::Char('v') => {
+                        // Show the level-selection screen
+                        showing_levels = true;
+                    }
+                    KeyCode::Char('l') => {
+                        // Show the leaderboard
+                        showing_leaderboard = true;
                     }
-                    KeyEvent::Up => selected_option = if selected_option > 0 {
+                    KeyCode::Char('s') => {
+                        // Show the settings screen
+                        showing_settings = true;
+                    }
+                    KeyCode::Up => selected_option = if selected_option > 0 {
                         selected_option - 1
                     } else {
                         menu_items.len() - 1
                     },
-                    KeyEvent::Down => selected_option = if selected_option >= menu_items.len() - 1 {
+                    KeyCode::Down => selected_option = if selected_option >= menu_items.len() - 1 {
                         0
                     } else {
                         selected_option + 1
                     },
                     _ => {}
                 }
-                Event::Tick => {}
             }
         }
     }
 
+    // Restore the terminal before exiting
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
     Ok(())
 }